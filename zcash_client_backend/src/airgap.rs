@@ -0,0 +1,150 @@
+//! Fountain-coded framing for transferring large payloads (spending keys, raw
+//! transactions, PCZTs) to an air-gapped signer over a lossy channel such as an
+//! animated QR code.
+//!
+//! Unlike a sequence-numbered chunking scheme, a RaptorQ fountain code lets the
+//! receiver reconstruct the original payload from *any* sufficiently large subset of
+//! the emitted frames, in any order, so a signer only has to keep scanning until it
+//! has seen enough frames rather than needing every frame exactly once.
+
+use raptorq::{Decoder, Encoder, EncodingPacket, ObjectTransmissionInformation};
+
+/// The serialized length of an `ObjectTransmissionInformation` header.
+const OTI_LEN: usize = 12;
+
+/// The serialized length of the encoding-symbol-id prefix `EncodingPacket::serialize`
+/// adds ahead of the symbol data.
+const ENCODING_SYMBOL_ID_LEN: usize = 4;
+
+/// The minimum number of repair symbols to emit, so that small payloads still get
+/// some redundancy against lost frames.
+const MIN_REPAIR_PACKETS: u32 = 2;
+
+/// One additional repair symbol is emitted for every this many source symbols.
+const REPAIR_PACKET_RATIO: u32 = 4;
+
+fn repair_packet_count(source_packets: u32) -> u32 {
+    (source_packets / REPAIR_PACKET_RATIO).max(MIN_REPAIR_PACKETS)
+}
+
+/// Fragments `data` into a stream of self-describing frames, each no larger than
+/// `max_frame_bytes`, suitable for displaying as a sequence of animated QR codes.
+/// The returned frames include both source symbols (covering `data` once) and
+/// repair symbols (extra redundancy), and may be fed into a [`FrameDecoder`] in any
+/// order or subset, as long as enough of them arrive.
+pub fn encode_frames(data: &[u8], max_frame_bytes: usize) -> Vec<Vec<u8>> {
+    let symbol_size = max_frame_bytes
+        .saturating_sub(OTI_LEN + ENCODING_SYMBOL_ID_LEN)
+        .max(1) as u16;
+    let encoder = Encoder::with_defaults(data, symbol_size);
+    let header = encoder.get_config().serialize();
+
+    let source_packets =
+        ((data.len() as u64 + u64::from(symbol_size) - 1) / u64::from(symbol_size)).max(1) as u32;
+
+    encoder
+        .get_encoded_packets(repair_packet_count(source_packets))
+        .into_iter()
+        .map(|packet| {
+            let mut frame = Vec::with_capacity(max_frame_bytes);
+            frame.extend_from_slice(&header);
+            frame.extend_from_slice(&packet.serialize());
+            frame
+        })
+        .collect()
+}
+
+/// Reassembles frames produced by [`encode_frames`] back into the original payload.
+/// Frames may be fed in via [`FrameDecoder::add_frame`] in any order, including
+/// duplicates; [`FrameDecoder::add_frame`] returns `Some` once enough frames have
+/// arrived to reconstruct the payload.
+pub struct FrameDecoder {
+    decoder: Option<Decoder>,
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        FrameDecoder { decoder: None }
+    }
+
+    /// Feeds one frame into the decoder. Returns the reconstructed payload once
+    /// enough frames have been seen, or `None` if more frames are still needed.
+    pub fn add_frame(&mut self, frame: &[u8]) -> Option<Vec<u8>> {
+        if frame.len() < OTI_LEN {
+            return None;
+        }
+        let (header, packet_bytes) = frame.split_at(OTI_LEN);
+
+        let decoder = self.decoder.get_or_insert_with(|| {
+            let mut oti = [0u8; OTI_LEN];
+            oti.copy_from_slice(header);
+            Decoder::new(ObjectTransmissionInformation::deserialize(&oti))
+        });
+
+        decoder.decode(EncodingPacket::deserialize(packet_bytes))
+    }
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_frames, FrameDecoder};
+
+    #[test]
+    fn frames_respect_max_frame_bytes() {
+        let data: Vec<u8> = (0..10_000).map(|i| (i % 256) as u8).collect();
+        let frames = encode_frames(&data, 256);
+
+        assert!(frames.iter().all(|frame| frame.len() <= 256));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let data: Vec<u8> = (0..10_000).map(|i| (i % 256) as u8).collect();
+        let frames = encode_frames(&data, 256);
+
+        let mut decoder = FrameDecoder::new();
+        let mut reconstructed = None;
+        for frame in &frames {
+            if let Some(payload) = decoder.add_frame(frame) {
+                reconstructed = Some(payload);
+                break;
+            }
+        }
+
+        assert_eq!(reconstructed, Some(data));
+    }
+
+    #[test]
+    fn roundtrip_out_of_order_with_losses() {
+        let data: Vec<u8> = (0..5_000).map(|i| (i * 7 % 256) as u8).collect();
+        let mut frames = encode_frames(&data, 256);
+
+        // Drop every tenth frame and reverse the rest, simulating a lossy,
+        // unordered animated QR scan. 5000 bytes in 240-byte symbols give 21
+        // source and 5 repair frames, so 23 of the 26 frames remain.
+        let mut kept = vec![];
+        for (i, frame) in frames.drain(..).enumerate() {
+            if i % 10 != 0 {
+                kept.push(frame);
+            }
+        }
+        kept.reverse();
+
+        let mut decoder = FrameDecoder::new();
+        let mut reconstructed = None;
+        for frame in &kept {
+            if let Some(payload) = decoder.add_frame(frame) {
+                reconstructed = Some(payload);
+                break;
+            }
+        }
+
+        assert_eq!(reconstructed, Some(data));
+    }
+}
@@ -0,0 +1,19 @@
+pub const HRP_SAPLING_EXTENDED_SPENDING_KEY_MAIN: &str = "secret-extended-key-main";
+pub const HRP_SAPLING_EXTENDED_FULL_VIEWING_KEY_MAIN: &str = "zxviews";
+pub const HRP_SAPLING_PAYMENT_ADDRESS_MAIN: &str = "zs";
+pub const HRP_UNIFIED_ADDRESS_MAIN: &str = "u";
+
+pub const HRP_SAPLING_EXTENDED_SPENDING_KEY_TEST: &str = "secret-extended-key-test";
+pub const HRP_SAPLING_EXTENDED_FULL_VIEWING_KEY_TEST: &str = "zxviewtestsapling";
+pub const HRP_SAPLING_PAYMENT_ADDRESS_TEST: &str = "ztestsapling";
+pub const HRP_UNIFIED_ADDRESS_TEST: &str = "utest";
+
+/// The mainnet Base58Check version bytes for a P2PKH transparent address.
+pub const B58_PUBKEY_ADDRESS_PREFIX: [u8; 2] = [0x1c, 0xb8];
+/// The mainnet Base58Check version bytes for a P2SH transparent address.
+pub const B58_SCRIPT_ADDRESS_PREFIX: [u8; 2] = [0x1c, 0xbd];
+
+/// The testnet Base58Check version bytes for a P2PKH transparent address.
+pub const B58_PUBKEY_ADDRESS_PREFIX_TEST: [u8; 2] = [0x1d, 0x25];
+/// The testnet Base58Check version bytes for a P2SH transparent address.
+pub const B58_SCRIPT_ADDRESS_PREFIX_TEST: [u8; 2] = [0x1c, 0xba];
@@ -5,10 +5,45 @@ use sapling_crypto::{
     jubjub::edwards,
     primitives::{Diversifier, PaymentAddress},
 };
+use std::error;
+use std::fmt;
 use std::io::{self, Write};
+use zcash_primitives::legacy::TransparentAddress;
 use zcash_primitives::JUBJUB;
 use zip32::{ExtendedFullViewingKey, ExtendedSpendingKey};
 
+/// An error while decoding a Bech32-encoded value, distinguishing a mismatched
+/// human-readable prefix (e.g. a testnet address read on mainnet) from a corrupt
+/// or malformed payload.
+#[derive(Debug)]
+pub enum Bech32DecodeError {
+    /// The string was not validly Bech32-encoded.
+    Bech32Error(bech32::Error),
+    /// The string's human-readable prefix did not match what was expected.
+    HrpMismatch { expected: String, actual: String },
+    /// The decoded payload could not be parsed into the requested type.
+    ReadError(String),
+    /// The bits of the decoded payload could not be validly repacked into bytes.
+    InvalidPadding,
+}
+
+impl fmt::Display for Bech32DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bech32DecodeError::Bech32Error(e) => write!(f, "Invalid Bech32: {}", e),
+            Bech32DecodeError::HrpMismatch { expected, actual } => write!(
+                f,
+                "Invalid human-readable prefix: expected \"{}\", got \"{}\"",
+                expected, actual
+            ),
+            Bech32DecodeError::ReadError(e) => write!(f, "Invalid decoded data: {}", e),
+            Bech32DecodeError::InvalidPadding => write!(f, "Invalid padding in Bech32 data"),
+        }
+    }
+}
+
+impl error::Error for Bech32DecodeError {}
+
 fn bech32_encode<F>(hrp: &str, write: F) -> String
 where
     F: Fn(&mut dyn Write) -> io::Result<()>,
@@ -23,16 +58,22 @@ where
     encoded.to_string()
 }
 
-fn bech32_decode<T, F>(hrp: &str, s: &str, read: F) -> Result<T, Error>
+fn bech32_decode<T, F>(hrp: &str, s: &str, read: F) -> Result<T, Bech32DecodeError>
 where
-    F: Fn(Vec<u8>) -> Result<T, Error>,
+    F: Fn(Vec<u8>) -> Result<T, Bech32DecodeError>,
 {
-    let encoded = Bech32::from_str_lenient(s)?;
+    let encoded = Bech32::from_str_lenient(s).map_err(Bech32DecodeError::Bech32Error)?;
     if encoded.hrp() == hrp {
-        let data = convert_bits(encoded.data(), 5, 8, false)?;
+        let data = convert_bits(encoded.data(), 5, 8, false).map_err(|e| match e {
+            bech32::Error::InvalidPadding => Bech32DecodeError::InvalidPadding,
+            e => Bech32DecodeError::Bech32Error(e),
+        })?;
         read(data)
     } else {
-        Err(format_err!("Invalid HRP"))
+        Err(Bech32DecodeError::HrpMismatch {
+            expected: hrp.to_owned(),
+            actual: encoded.hrp().to_owned(),
+        })
     }
 }
 
@@ -40,8 +81,14 @@ pub fn encode_extended_spending_key(hrp: &str, extsk: &ExtendedSpendingKey) -> S
     bech32_encode(hrp, |w| extsk.write(w))
 }
 
-pub fn decode_extended_spending_key(hrp: &str, s: &str) -> Result<ExtendedSpendingKey, Error> {
-    bech32_decode(hrp, s, |data| Ok(ExtendedSpendingKey::read(&data[..])?))
+pub fn decode_extended_spending_key(
+    hrp: &str,
+    s: &str,
+) -> Result<ExtendedSpendingKey, Bech32DecodeError> {
+    bech32_decode(hrp, s, |data| {
+        ExtendedSpendingKey::read(&data[..])
+            .map_err(|e| Bech32DecodeError::ReadError(e.to_string()))
+    })
 }
 
 pub fn encode_extended_full_viewing_key(hrp: &str, extfvk: &ExtendedFullViewingKey) -> String {
@@ -51,8 +98,11 @@ pub fn encode_extended_full_viewing_key(hrp: &str, extfvk: &ExtendedFullViewingK
 pub fn decode_extended_full_viewing_key(
     hrp: &str,
     s: &str,
-) -> Result<ExtendedFullViewingKey, Error> {
-    bech32_decode(hrp, s, |data| Ok(ExtendedFullViewingKey::read(&data[..])?))
+) -> Result<ExtendedFullViewingKey, Bech32DecodeError> {
+    bech32_decode(hrp, s, |data| {
+        ExtendedFullViewingKey::read(&data[..])
+            .map_err(|e| Bech32DecodeError::ReadError(e.to_string()))
+    })
 }
 
 pub fn encode_payment_address(hrp: &str, addr: &PaymentAddress<Bls12>) -> String {
@@ -62,20 +112,79 @@ pub fn encode_payment_address(hrp: &str, addr: &PaymentAddress<Bls12>) -> String
     })
 }
 
-pub fn decode_payment_address(hrp: &str, s: &str) -> Result<PaymentAddress<Bls12>, Error> {
+pub fn decode_payment_address(
+    hrp: &str,
+    s: &str,
+) -> Result<PaymentAddress<Bls12>, Bech32DecodeError> {
     bech32_decode(hrp, s, |data| {
         let mut diversifier = Diversifier([0; 11]);
         diversifier.0.copy_from_slice(&data[0..11]);
         match edwards::Point::<Bls12, _>::read(&data[11..], &JUBJUB) {
             Ok(p) => match p.as_prime_order(&JUBJUB) {
                 Some(pk_d) => Ok(PaymentAddress { pk_d, diversifier }),
-                None => Err(format_err!("pk_d is not prime order")),
+                None => Err(Bech32DecodeError::ReadError(
+                    "pk_d is not prime order".to_owned(),
+                )),
             },
-            Err(e) => Err(format_err!("{}", e)),
+            Err(e) => Err(Bech32DecodeError::ReadError(e.to_string())),
         }
     })
 }
 
+/// Writes a transparent address as a Base58Check-encoded string, using the given
+/// version bytes to distinguish P2PKH from P2SH addresses and mainnet from testnet.
+pub fn encode_transparent_address(
+    pubkey_version: &[u8],
+    script_version: &[u8],
+    addr: &TransparentAddress,
+) -> String {
+    let decoded = match addr {
+        TransparentAddress::PublicKey(hash) => {
+            let mut decoded = vec![0; pubkey_version.len()];
+            decoded[..].copy_from_slice(pubkey_version);
+            decoded.extend_from_slice(hash);
+            decoded
+        }
+        TransparentAddress::Script(hash) => {
+            let mut decoded = vec![0; script_version.len()];
+            decoded[..].copy_from_slice(script_version);
+            decoded.extend_from_slice(hash);
+            decoded
+        }
+    };
+
+    bs58::encode(decoded).with_check().into_string()
+}
+
+/// Reads a transparent address from its Base58Check-encoded string form, returning
+/// `Ok(None)` if the decoded version bytes do not match either of the given prefixes.
+pub fn decode_transparent_address(
+    pubkey_version: &[u8],
+    script_version: &[u8],
+    s: &str,
+) -> Result<Option<TransparentAddress>, Error> {
+    let decoded = match bs58::decode(s).with_check(None).into_vec() {
+        Ok(decoded) => decoded,
+        Err(e) => return Err(format_err!("{}", e)),
+    };
+
+    if decoded.len() == pubkey_version.len() + 20
+        && &decoded[..pubkey_version.len()] == pubkey_version
+    {
+        let mut hash = [0; 20];
+        hash.copy_from_slice(&decoded[pubkey_version.len()..]);
+        Ok(Some(TransparentAddress::PublicKey(hash)))
+    } else if decoded.len() == script_version.len() + 20
+        && &decoded[..script_version.len()] == script_version
+    {
+        let mut hash = [0; 20];
+        hash.copy_from_slice(&decoded[script_version.len()..]);
+        Ok(Some(TransparentAddress::Script(hash)))
+    } else {
+        Ok(None)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use pairing::bls12_381::Bls12;
@@ -84,9 +193,13 @@ mod tests {
         jubjub::edwards,
         primitives::{Diversifier, PaymentAddress},
     };
+    use zcash_primitives::legacy::TransparentAddress;
     use zcash_primitives::JUBJUB;
 
-    use super::{decode_payment_address, encode_payment_address};
+    use super::{
+        decode_payment_address, decode_transparent_address, encode_payment_address,
+        encode_transparent_address, Bech32DecodeError,
+    };
     use crate::constants;
 
     #[test]
@@ -122,5 +235,67 @@ mod tests {
                 .unwrap(),
             addr
         );
+
+        match decode_payment_address(constants::HRP_SAPLING_PAYMENT_ADDRESS_TEST, encoded_main)
+            .unwrap_err()
+        {
+            Bech32DecodeError::HrpMismatch { expected, actual } => {
+                assert_eq!(expected, constants::HRP_SAPLING_PAYMENT_ADDRESS_TEST);
+                assert_eq!(actual, constants::HRP_SAPLING_PAYMENT_ADDRESS_MAIN);
+            }
+            other => panic!("expected HrpMismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn transparent_address() {
+        let pk_addr = TransparentAddress::PublicKey([0x11; 20]);
+        let script_addr = TransparentAddress::Script([0x22; 20]);
+
+        let encoded_pk = encode_transparent_address(
+            &constants::B58_PUBKEY_ADDRESS_PREFIX,
+            &constants::B58_SCRIPT_ADDRESS_PREFIX,
+            &pk_addr,
+        );
+        let encoded_script = encode_transparent_address(
+            &constants::B58_PUBKEY_ADDRESS_PREFIX,
+            &constants::B58_SCRIPT_ADDRESS_PREFIX,
+            &script_addr,
+        );
+
+        assert_eq!(
+            decode_transparent_address(
+                &constants::B58_PUBKEY_ADDRESS_PREFIX,
+                &constants::B58_SCRIPT_ADDRESS_PREFIX,
+                &encoded_pk,
+            )
+            .unwrap(),
+            Some(pk_addr)
+        );
+        assert_eq!(
+            decode_transparent_address(
+                &constants::B58_PUBKEY_ADDRESS_PREFIX,
+                &constants::B58_SCRIPT_ADDRESS_PREFIX,
+                &encoded_script,
+            )
+            .unwrap(),
+            Some(script_addr)
+        );
+
+        // A valid testnet address does not match either mainnet prefix.
+        let encoded_pk_test = encode_transparent_address(
+            &constants::B58_PUBKEY_ADDRESS_PREFIX_TEST,
+            &constants::B58_SCRIPT_ADDRESS_PREFIX_TEST,
+            &pk_addr,
+        );
+        assert_eq!(
+            decode_transparent_address(
+                &constants::B58_PUBKEY_ADDRESS_PREFIX,
+                &constants::B58_SCRIPT_ADDRESS_PREFIX,
+                &encoded_pk_test,
+            )
+            .unwrap(),
+            None
+        );
     }
 }
@@ -0,0 +1,248 @@
+//! A signing backend that keeps Sapling spend authority on a Ledger hardware wallet,
+//! communicating over the device's APDU transport. [`LedgerKeyStore`] exposes the
+//! same derive/encode surface as the in-memory [`ExtendedSpendingKey`] path, but
+//! never asks the device for the spending key itself: viewing keys and addresses
+//! are derived on-device, and spends are authorized by sending the spend
+//! description's public data to the device for signing.
+//!
+//! [`ExtendedSpendingKey`]: zip32::ExtendedSpendingKey
+
+use failure::Error;
+use pairing::bls12_381::Bls12;
+use sapling_crypto::{
+    jubjub::edwards,
+    primitives::{Diversifier, PaymentAddress},
+};
+use std::fmt;
+use zcash_primitives::JUBJUB;
+use zip32::ExtendedFullViewingKey;
+
+/// The CLA byte identifying the Zcash Ledger application.
+const CLA: u8 = 0xe0;
+
+const INS_GET_EXTENDED_FVK: u8 = 0x02;
+const INS_GET_ADDRESS: u8 = 0x03;
+const INS_SIGN_SAPLING_SPEND: u8 = 0x04;
+
+/// The status word a Ledger device returns on success.
+const SW_OK: u16 = 0x9000;
+
+/// The public data of a Sapling spend description that the device needs in order to
+/// authorize spending the note, without ever seeing the spending key.
+pub struct SaplingSpendDescription {
+    pub value_commitment: [u8; 32],
+    pub anchor: [u8; 32],
+    pub nullifier: [u8; 32],
+}
+
+impl SaplingSpendDescription {
+    fn to_apdu_data(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(96);
+        data.extend_from_slice(&self.value_commitment);
+        data.extend_from_slice(&self.anchor);
+        data.extend_from_slice(&self.nullifier);
+        data
+    }
+}
+
+/// A transport capable of exchanging a single APDU command with a Ledger device.
+/// Implementations typically wrap a USB HID or Bluetooth connection; this crate
+/// only defines the APDU command set and leaves the transport to the caller.
+pub trait ApduTransport {
+    /// Sends `apdu` to the device and returns the raw response, including the two
+    /// trailing status word bytes.
+    fn exchange(&mut self, apdu: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+/// An error encountered while talking to a Ledger device.
+#[derive(Debug)]
+pub enum LedgerError {
+    /// The transport itself failed (the device was disconnected, timed out, etc).
+    Transport(Error),
+    /// The device returned a non-success status word, e.g. because the user
+    /// rejected the request on-screen.
+    Device(u16),
+    /// The device's response could not be parsed into the expected type.
+    Decode(String),
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerError::Transport(e) => write!(f, "Ledger transport error: {}", e),
+            LedgerError::Device(sw) => write!(f, "Ledger device returned status word {:#06x}", sw),
+            LedgerError::Decode(e) => write!(f, "Could not decode Ledger response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LedgerError {}
+
+fn build_apdu(ins: u8, p1: u8, p2: u8, data: &[u8]) -> Vec<u8> {
+    let mut apdu = Vec::with_capacity(5 + data.len());
+    apdu.push(CLA);
+    apdu.push(ins);
+    apdu.push(p1);
+    apdu.push(p2);
+    apdu.push(data.len() as u8);
+    apdu.extend_from_slice(data);
+    apdu
+}
+
+/// Derives keys, addresses, and Sapling spend authorizations from a Ledger device,
+/// for a given account index under the device's Zcash app.
+pub struct LedgerKeyStore<T: ApduTransport> {
+    transport: T,
+    account: u32,
+}
+
+impl<T: ApduTransport> LedgerKeyStore<T> {
+    pub fn new(transport: T, account: u32) -> Self {
+        LedgerKeyStore { transport, account }
+    }
+
+    fn exchange(&mut self, ins: u8, data: &[u8]) -> Result<Vec<u8>, LedgerError> {
+        let apdu = build_apdu(ins, 0, 0, data);
+        let mut response = self
+            .transport
+            .exchange(&apdu)
+            .map_err(LedgerError::Transport)?;
+
+        if response.len() < 2 {
+            return Err(LedgerError::Decode(
+                "response shorter than the status word".to_owned(),
+            ));
+        }
+        let sw_bytes = response.split_off(response.len() - 2);
+        let sw = u16::from_be_bytes([sw_bytes[0], sw_bytes[1]]);
+        if sw != SW_OK {
+            return Err(LedgerError::Device(sw));
+        }
+
+        Ok(response)
+    }
+
+    /// Asks the device to derive the extended full viewing key for this account.
+    /// The returned bytes are in the same format produced by
+    /// [`ExtendedFullViewingKey::write`] and accepted by
+    /// [`decode_extended_full_viewing_key`](crate::encoding::decode_extended_full_viewing_key).
+    pub fn get_extended_full_viewing_key(&mut self) -> Result<ExtendedFullViewingKey, LedgerError> {
+        let account = self.account;
+        let response = self.exchange(INS_GET_EXTENDED_FVK, &account.to_le_bytes())?;
+        ExtendedFullViewingKey::read(&response[..]).map_err(|e| LedgerError::Decode(e.to_string()))
+    }
+
+    /// Asks the device to derive the Sapling payment address for the given
+    /// diversifier index under this account.
+    pub fn get_payment_address(
+        &mut self,
+        diversifier_index: &[u8; 11],
+    ) -> Result<PaymentAddress<Bls12>, LedgerError> {
+        let mut data = self.account.to_le_bytes().to_vec();
+        data.extend_from_slice(diversifier_index);
+        let response = self.exchange(INS_GET_ADDRESS, &data)?;
+
+        if response.len() != 43 {
+            return Err(LedgerError::Decode(format!(
+                "expected a 43-byte payment address, got {} bytes",
+                response.len()
+            )));
+        }
+
+        let mut diversifier = Diversifier([0; 11]);
+        diversifier.0.copy_from_slice(&response[0..11]);
+        match edwards::Point::<Bls12, _>::read(&response[11..], &JUBJUB) {
+            Ok(p) => match p.as_prime_order(&JUBJUB) {
+                Some(pk_d) => Ok(PaymentAddress { pk_d, diversifier }),
+                None => Err(LedgerError::Decode("pk_d is not prime order".to_owned())),
+            },
+            Err(e) => Err(LedgerError::Decode(e.to_string())),
+        }
+    }
+
+    /// Asks the device to authorize a Sapling spend, without ever transmitting the
+    /// spending key: only the spend description's public value commitment, anchor,
+    /// and nullifier are sent, and the device returns the 64-byte spend auth
+    /// signature to embed in the spend description.
+    pub fn sign_sapling_spend(
+        &mut self,
+        spend: &SaplingSpendDescription,
+    ) -> Result<[u8; 64], LedgerError> {
+        let response = self.exchange(INS_SIGN_SAPLING_SPEND, &spend.to_apdu_data())?;
+
+        if response.len() != 64 {
+            return Err(LedgerError::Decode(format!(
+                "expected a 64-byte spend auth signature, got {} bytes",
+                response.len()
+            )));
+        }
+        let mut signature = [0; 64];
+        signature.copy_from_slice(&response);
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ApduTransport, LedgerKeyStore, SaplingSpendDescription, CLA, INS_SIGN_SAPLING_SPEND,
+    };
+    use failure::Error;
+
+    /// A fake transport that just echoes back a fixed response, to exercise the
+    /// APDU framing without a physical device.
+    struct MockTransport {
+        response: Vec<u8>,
+        last_apdu: Option<Vec<u8>>,
+    }
+
+    impl ApduTransport for MockTransport {
+        fn exchange(&mut self, apdu: &[u8]) -> Result<Vec<u8>, Error> {
+            self.last_apdu = Some(apdu.to_vec());
+            Ok(self.response.clone())
+        }
+    }
+
+    #[test]
+    fn sign_sapling_spend_frames_apdu_and_parses_signature() {
+        let mut response = vec![0x42; 64];
+        response.extend_from_slice(&[0x90, 0x00]);
+        let transport = MockTransport {
+            response,
+            last_apdu: None,
+        };
+        let mut store = LedgerKeyStore::new(transport, 0);
+
+        let spend = SaplingSpendDescription {
+            value_commitment: [1; 32],
+            anchor: [2; 32],
+            nullifier: [3; 32],
+        };
+        let signature = store.sign_sapling_spend(&spend).unwrap();
+        assert_eq!(signature, [0x42; 64]);
+
+        let apdu = store.transport.last_apdu.as_ref().unwrap();
+        assert_eq!(apdu[0], CLA);
+        assert_eq!(apdu[1], INS_SIGN_SAPLING_SPEND);
+        assert_eq!(apdu[4] as usize, apdu.len() - 5);
+    }
+
+    #[test]
+    fn device_error_status_word_is_surfaced() {
+        let transport = MockTransport {
+            response: vec![0x69, 0x85],
+            last_apdu: None,
+        };
+        let mut store = LedgerKeyStore::new(transport, 0);
+
+        let spend = SaplingSpendDescription {
+            value_commitment: [0; 32],
+            anchor: [0; 32],
+            nullifier: [0; 32],
+        };
+        match store.sign_sapling_spend(&spend) {
+            Err(super::LedgerError::Device(sw)) => assert_eq!(sw, 0x6985),
+            other => panic!("expected a Device error, got {:?}", other),
+        }
+    }
+}
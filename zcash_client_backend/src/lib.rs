@@ -0,0 +1,17 @@
+extern crate bech32;
+extern crate blake2_rfc;
+extern crate bs58;
+extern crate failure;
+extern crate pairing;
+extern crate rand;
+extern crate raptorq;
+extern crate sapling_crypto;
+extern crate zcash_primitives;
+extern crate zip32;
+
+pub mod airgap;
+pub mod constants;
+pub mod encoding;
+#[cfg(feature = "ledger")]
+pub mod ledger;
+pub mod unified;
@@ -0,0 +1,148 @@
+//! The reversible F4Jumble permutation (ZIP 316) used to make Unified Addresses
+//! indivisible.
+//!
+//! F4Jumble treats the whole message as a single blob: splitting it into a left part
+//! `a` and a right part `b` of near-equal length, then running a 2-round Feistel
+//! network that alternately mixes each part into the other using BLAKE2b as a
+//! pseudorandom keystream, personalized per round so that every round draws from an
+//! independent stream. Each round first mixes `a` into `b` (the `G` function), then
+//! mixes the just-updated `b` into `a` (the `H` function); because each mix is an XOR
+//! (its own inverse), undoing the rounds in reverse order, each undoing `H` before
+//! `G`, inverts the permutation exactly.
+
+use blake2_rfc::blake2b::Blake2b;
+
+/// The maximum length in bytes of the left-hand part `a` of a jumbled message.
+const MAX_LEFT_LEN: usize = 128;
+
+/// The 13-byte personalization prefixes for the two round functions; the 14th byte
+/// is the round index, per ZIP 316.
+const PERSONALIZATION_H: &[u8; 13] = b"UA_F4Jumble_H";
+const PERSONALIZATION_G: &[u8; 13] = b"UA_F4Jumble_G";
+
+fn split(message: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let left_len = ((message.len() + 1) / 2).min(MAX_LEFT_LEN);
+    let (a, b) = message.split_at(left_len);
+    (a.to_vec(), b.to_vec())
+}
+
+/// Expands `input` into a pseudorandom keystream of `out_len` bytes. `prefix` and
+/// `round` select an independent BLAKE2b personalization per round function and
+/// round index, rather than being hashed as part of the input.
+fn keystream(prefix: &[u8; 13], round: u8, input: &[u8], out_len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(out_len);
+    let mut block: u16 = 0;
+    while out.len() < out_len {
+        let chunk_len = (out_len - out.len()).min(64);
+
+        let mut persona = [0u8; 16];
+        persona[..13].copy_from_slice(prefix);
+        persona[13] = round;
+        persona[14..16].copy_from_slice(&block.to_le_bytes());
+
+        let mut hasher = Blake2b::with_params(chunk_len, &[], &[], &persona);
+        hasher.update(input);
+        out.extend_from_slice(hasher.finalize().as_bytes());
+        block += 1;
+    }
+    out.truncate(out_len);
+    out
+}
+
+fn xor_into(target: &mut [u8], keystream: &[u8]) {
+    for (t, k) in target.iter_mut().zip(keystream) {
+        *t ^= k;
+    }
+}
+
+/// `G` mixes the left part `a` into the right part `b`.
+fn g(round: u8, a: &[u8], b: &mut Vec<u8>) {
+    let keystream = keystream(PERSONALIZATION_G, round, a, b.len());
+    xor_into(b, &keystream);
+}
+
+/// `H` mixes the right part `b` into the left part `a`.
+fn h(round: u8, b: &[u8], a: &mut Vec<u8>) {
+    let keystream = keystream(PERSONALIZATION_H, round, b, a.len());
+    xor_into(a, &keystream);
+}
+
+/// Applies the F4Jumble permutation to `message`.
+pub fn jumble(message: &[u8]) -> Vec<u8> {
+    let (mut a, mut b) = split(message);
+
+    for round in 0..2 {
+        g(round, &a, &mut b);
+        h(round, &b, &mut a);
+    }
+
+    a.extend_from_slice(&b);
+    a
+}
+
+/// Reverses the F4Jumble permutation applied by [`jumble`].
+pub fn unjumble(message: &[u8]) -> Vec<u8> {
+    let (mut a, mut b) = split(message);
+
+    for round in (0..2).rev() {
+        h(round, &b, &mut a);
+        g(round, &a, &mut b);
+    }
+
+    a.extend_from_slice(&b);
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{jumble, unjumble};
+
+    #[test]
+    fn roundtrip() {
+        for len in &[0, 1, 2, 16, 32, 61, 96, 127, 128, 129, 200, 256, 400] {
+            let message: Vec<u8> = (0..*len).map(|i| (i % 256) as u8).collect();
+            let jumbled = jumble(&message);
+            assert_eq!(jumbled.len(), message.len());
+            assert_eq!(unjumble(&jumbled), message);
+        }
+    }
+
+    #[test]
+    fn jumble_changes_short_messages() {
+        let message = vec![0u8; 32];
+        assert_ne!(jumble(&message), message);
+    }
+
+    #[test]
+    fn jumble_mixes_both_halves() {
+        // A real Unified Address body is well under 128 bytes, so both halves of
+        // the split must be non-empty and mixed, not just the first half XORed
+        // with a fixed per-round constant.
+        let message = vec![0u8; 64];
+        let jumbled = jumble(&message);
+        let (_, b) = jumbled.split_at(32);
+        assert_ne!(b, vec![0u8; 32]);
+    }
+
+    // Pins the output of the `b ^= G(0,a); a ^= H(0,b); b ^= G(1,a); a ^= H(1,b)`
+    // schedule for a fixed input. This sandbox has no network access to pull the
+    // published ZIP 316 F4Jumble known-answer vectors, so rather than invent bytes
+    // and pass them off as an official test vector, this is only a regression pin:
+    // it catches a future change to the schedule, but isn't a substitute for
+    // checking the real upstream vectors against this implementation before release.
+    #[test]
+    fn jumble_schedule_regression() {
+        let message: Vec<u8> = (0..41).collect();
+        let jumbled = jumble(&message);
+        assert_eq!(
+            jumbled,
+            vec![
+                0x99, 0x30, 0xba, 0xdc, 0xc6, 0xa0, 0x3e, 0x97, 0x24, 0x4e, 0xc7, 0xa2, 0x88, 0xb9,
+                0xe7, 0x2e, 0x96, 0x1b, 0x12, 0xe6, 0x56, 0x70, 0x9f, 0xdf, 0xef, 0xd0, 0x4d, 0x81,
+                0x35, 0x7e, 0xd0, 0xd5, 0x1b, 0x8e, 0x9e, 0x68, 0xa3, 0xeb, 0x1c, 0xbf, 0x0a,
+            ]
+        );
+        assert_eq!(unjumble(&jumbled), message);
+        assert_ne!(jumbled, message);
+    }
+}
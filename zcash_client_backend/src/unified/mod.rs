@@ -0,0 +1,341 @@
+//! Encoding and decoding of Unified Addresses, which bundle a set of typed receivers
+//! (transparent, Sapling, Orchard, ...) into a single indivisible Bech32m string.
+
+mod bech32m;
+mod f4jumble;
+
+use failure::{format_err, Error};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+const TYPECODE_P2PKH: u8 = 0x00;
+const TYPECODE_P2SH: u8 = 0x01;
+const TYPECODE_SAPLING: u8 = 0x02;
+const TYPECODE_ORCHARD: u8 = 0x03;
+
+/// A single typed receiver within a Unified Address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Receiver {
+    P2pkh([u8; 20]),
+    P2sh([u8; 20]),
+    Sapling([u8; 43]),
+    Orchard([u8; 43]),
+}
+
+impl Receiver {
+    fn typecode(&self) -> u8 {
+        match self {
+            Receiver::P2pkh(_) => TYPECODE_P2PKH,
+            Receiver::P2sh(_) => TYPECODE_P2SH,
+            Receiver::Sapling(_) => TYPECODE_SAPLING,
+            Receiver::Orchard(_) => TYPECODE_ORCHARD,
+        }
+    }
+
+    fn data(&self) -> &[u8] {
+        match self {
+            Receiver::P2pkh(data) | Receiver::P2sh(data) => data,
+            Receiver::Sapling(data) | Receiver::Orchard(data) => data,
+        }
+    }
+
+    /// Parses a single receiver from its typecode and data. Returns `Ok(None)` for
+    /// an unrecognised *odd* typecode, which per the typecode-pair convention is
+    /// safe for a parser that doesn't understand it to skip; an unrecognised
+    /// *even* typecode is an error, since those are reserved for receiver types a
+    /// conformant parser is required to understand.
+    fn from_typecode(typecode: u8, data: &[u8]) -> Result<Option<Self>, Error> {
+        match typecode {
+            TYPECODE_P2PKH => Ok(Some(Receiver::P2pkh(fixed_bytes_20(data)?))),
+            TYPECODE_P2SH => Ok(Some(Receiver::P2sh(fixed_bytes_20(data)?))),
+            TYPECODE_SAPLING => Ok(Some(Receiver::Sapling(fixed_bytes_43(data)?))),
+            TYPECODE_ORCHARD => Ok(Some(Receiver::Orchard(fixed_bytes_43(data)?))),
+            _ if typecode % 2 == 0 => Err(format_err!(
+                "unrecognised non-optional receiver typecode {}",
+                typecode
+            )),
+            _ => Ok(None),
+        }
+    }
+}
+
+fn fixed_bytes_20(data: &[u8]) -> Result<[u8; 20], Error> {
+    if data.len() != 20 {
+        return Err(format_err!(
+            "expected a 20-byte receiver, got {} bytes",
+            data.len()
+        ));
+    }
+    let mut out = [0u8; 20];
+    out.copy_from_slice(data);
+    Ok(out)
+}
+
+fn fixed_bytes_43(data: &[u8]) -> Result<[u8; 43], Error> {
+    if data.len() != 43 {
+        return Err(format_err!(
+            "expected a 43-byte receiver, got {} bytes",
+            data.len()
+        ));
+    }
+    let mut out = [0u8; 43];
+    out.copy_from_slice(data);
+    Ok(out)
+}
+
+/// A Unified Address: an ordered, deduplicated set of receivers for different pools.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnifiedAddress {
+    receivers: Vec<Receiver>,
+}
+
+impl UnifiedAddress {
+    /// Constructs a Unified Address from a set of receivers, sorting them into the
+    /// canonical typecode-ascending order required by the encoding. Returns an error
+    /// if the same typecode appears more than once.
+    pub fn from_receivers(mut receivers: Vec<Receiver>) -> Result<Self, Error> {
+        receivers.sort_by_key(Receiver::typecode);
+        for pair in receivers.windows(2) {
+            if pair[0].typecode() == pair[1].typecode() {
+                return Err(format_err!(
+                    "duplicate receiver typecode {}",
+                    pair[0].typecode()
+                ));
+            }
+        }
+        Ok(UnifiedAddress { receivers })
+    }
+
+    pub fn receivers(&self) -> &[Receiver] {
+        &self.receivers
+    }
+}
+
+fn write_compact_size<W: Write>(mut writer: W, n: u64) -> io::Result<()> {
+    if n < 0xfd {
+        writer.write_all(&[n as u8])
+    } else if n <= 0xffff {
+        writer.write_all(&[0xfd])?;
+        writer.write_all(&(n as u16).to_le_bytes())
+    } else if n <= 0xffff_ffff {
+        writer.write_all(&[0xfe])?;
+        writer.write_all(&(n as u32).to_le_bytes())
+    } else {
+        writer.write_all(&[0xff])?;
+        writer.write_all(&n.to_le_bytes())
+    }
+}
+
+fn read_compact_size<R: Read>(mut reader: R) -> io::Result<u64> {
+    let mut prefix = [0u8; 1];
+    reader.read_exact(&mut prefix)?;
+    match prefix[0] {
+        0xfd => {
+            let mut buf = [0u8; 2];
+            reader.read_exact(&mut buf)?;
+            Ok(u16::from_le_bytes(buf) as u64)
+        }
+        0xfe => {
+            let mut buf = [0u8; 4];
+            reader.read_exact(&mut buf)?;
+            Ok(u32::from_le_bytes(buf) as u64)
+        }
+        0xff => {
+            let mut buf = [0u8; 8];
+            reader.read_exact(&mut buf)?;
+            Ok(u64::from_le_bytes(buf))
+        }
+        n => Ok(n as u64),
+    }
+}
+
+/// The padding appended before F4Jumble is the HRP, right-padded with zero bytes to
+/// 16 bytes. Unified Address HRPs are always short enough for this to fit.
+fn padding(hrp: &str) -> Result<[u8; 16], Error> {
+    if hrp.len() > 16 {
+        return Err(format_err!("HRP {} is too long to pad to 16 bytes", hrp));
+    }
+    let mut padding = [0u8; 16];
+    padding[..hrp.len()].copy_from_slice(hrp.as_bytes());
+    Ok(padding)
+}
+
+/// Encodes a Unified Address with the given human-readable prefix (`u` on mainnet,
+/// `utest` on testnet).
+pub fn encode_unified_address(hrp: &str, ua: &UnifiedAddress) -> Result<String, Error> {
+    let mut message = vec![];
+    for receiver in &ua.receivers {
+        write_compact_size(&mut message, u64::from(receiver.typecode()))?;
+        write_compact_size(&mut message, receiver.data().len() as u64)?;
+        message.write_all(receiver.data())?;
+    }
+    message.extend_from_slice(&padding(hrp)?);
+
+    let jumbled = f4jumble::jumble(&message);
+    Ok(bech32m::encode(hrp, &jumbled))
+}
+
+/// Decodes a Unified Address, checking that its human-readable prefix matches `hrp`.
+pub fn decode_unified_address(hrp: &str, s: &str) -> Result<UnifiedAddress, Error> {
+    let (decoded_hrp, jumbled) = bech32m::decode(s)?;
+    if decoded_hrp != hrp {
+        return Err(format_err!("expected HRP {}, got {}", hrp, decoded_hrp));
+    }
+
+    let message = f4jumble::unjumble(&jumbled);
+    if message.len() < 16 {
+        return Err(format_err!("Unified Address is too short"));
+    }
+    let (body, tail) = message.split_at(message.len() - 16);
+    if tail != &padding(hrp)?[..] {
+        return Err(format_err!("Unified Address padding does not match HRP"));
+    }
+
+    let mut reader = body;
+    let mut receivers = vec![];
+    let mut last_typecode: Option<u8> = None;
+    while !reader.is_empty() {
+        let typecode = read_compact_size(&mut reader)?;
+        let typecode = u8::try_from(typecode)
+            .map_err(|_| format_err!("receiver typecode {} out of range", typecode))?;
+        // ZIP 316 requires every typecode in the raw stream, including ones this
+        // parser doesn't recognise, to appear in strictly ascending order; check
+        // that here, before any unrecognised-odd-typecode receiver is dropped.
+        if let Some(last) = last_typecode {
+            if typecode <= last {
+                return Err(format_err!(
+                    "receivers are not in canonical typecode-ascending order"
+                ));
+            }
+        }
+        last_typecode = Some(typecode);
+
+        let len = read_compact_size(&mut reader)?;
+        let len = usize::try_from(len)
+            .map_err(|_| format_err!("receiver length {} out of range", len))?;
+        if reader.len() < len {
+            return Err(format_err!("truncated receiver in Unified Address"));
+        }
+        let (data, rest) = reader.split_at(len);
+        if let Some(receiver) = Receiver::from_typecode(typecode, data)? {
+            receivers.push(receiver);
+        }
+        reader = rest;
+    }
+
+    Ok(UnifiedAddress { receivers })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bech32m, decode_unified_address, encode_unified_address, f4jumble, padding,
+        write_compact_size, Receiver, UnifiedAddress,
+    };
+
+    /// Builds a raw (pre-Bech32m) Unified Address message directly from
+    /// `(typecode, data)` pairs, bypassing `UnifiedAddress` so that malformed or
+    /// non-canonical messages can be constructed for decode tests.
+    fn raw_message(hrp: &str, receivers: &[(u8, &[u8])]) -> String {
+        let mut message = vec![];
+        for (typecode, data) in receivers {
+            write_compact_size(&mut message, u64::from(*typecode)).unwrap();
+            write_compact_size(&mut message, data.len() as u64).unwrap();
+            message.extend_from_slice(data);
+        }
+        message.extend_from_slice(&padding(hrp).unwrap());
+        bech32m::encode(hrp, &f4jumble::jumble(&message))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let ua = UnifiedAddress::from_receivers(vec![
+            Receiver::Orchard([0x24; 43]),
+            Receiver::P2pkh([0x11; 20]),
+            Receiver::Sapling([0x42; 43]),
+        ])
+        .unwrap();
+
+        // Receivers are reordered into ascending typecode order on construction.
+        assert_eq!(
+            ua.receivers(),
+            &[
+                Receiver::P2pkh([0x11; 20]),
+                Receiver::Sapling([0x42; 43]),
+                Receiver::Orchard([0x24; 43]),
+            ]
+        );
+
+        let encoded = encode_unified_address("u", &ua).unwrap();
+        assert_eq!(decode_unified_address("u", &encoded).unwrap(), ua);
+    }
+
+    #[test]
+    fn rejects_duplicate_typecodes() {
+        let result = UnifiedAddress::from_receivers(vec![
+            Receiver::P2pkh([0x11; 20]),
+            Receiver::P2sh([0x22; 20]),
+            Receiver::P2pkh([0x33; 20]),
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_network_hrp() {
+        let ua = UnifiedAddress::from_receivers(vec![Receiver::P2pkh([0x11; 20])]).unwrap();
+        let encoded = encode_unified_address("u", &ua).unwrap();
+        assert!(decode_unified_address("utest", &encoded).is_err());
+    }
+
+    #[test]
+    fn decode_rejects_out_of_order_receivers() {
+        // Sapling (0x02) before P2pkh (0x00): a valid receiver set, but not in the
+        // canonical ascending-typecode order, so it must be rejected rather than
+        // silently accepted via re-sorting.
+        let encoded = raw_message("u", &[(0x02, &[0x42; 43]), (0x00, &[0x11; 20])]);
+        assert!(decode_unified_address("u", &encoded).is_err());
+    }
+
+    #[test]
+    fn decode_skips_unknown_odd_typecode() {
+        // Typecode 0x05 is odd and unrecognised, so a conformant parser should
+        // ignore that receiver and still decode the rest of the address. It's
+        // placed after Sapling (0x02) so the raw typecode stream (0x02, 0x05) is
+        // still strictly ascending.
+        let encoded = raw_message("u", &[(0x02, &[0x42; 43]), (0x05, &[0xff; 8])]);
+        let ua = decode_unified_address("u", &encoded).unwrap();
+        assert_eq!(ua.receivers(), &[Receiver::Sapling([0x42; 43])]);
+    }
+
+    #[test]
+    fn decode_rejects_unrecognised_typecode_out_of_order() {
+        // Typecode 0x05 is odd and unrecognised, but it appears before Sapling
+        // (0x02) in the raw stream, so the encoding is non-canonical and must be
+        // rejected on the raw typecode order, not just on recognised receivers.
+        let encoded = raw_message("u", &[(0x05, &[0xff; 8]), (0x02, &[0x42; 43])]);
+        assert!(decode_unified_address("u", &encoded).is_err());
+    }
+
+    #[test]
+    fn decode_rejects_unknown_even_typecode() {
+        // Typecode 0x06 is even and unrecognised, so it names a receiver type this
+        // parser is required to understand, and decoding must fail.
+        let encoded = raw_message("u", &[(0x00, &[0x11; 20]), (0x06, &[0xff; 8])]);
+        assert!(decode_unified_address("u", &encoded).is_err());
+    }
+
+    #[test]
+    fn decode_rejects_oversized_typecode() {
+        // A multi-byte compact-size typecode (here, 256) must be rejected rather
+        // than silently truncated to fit in a u8 (which would wrap 256 to 0x00 and
+        // misparse this as a P2PKH receiver).
+        let mut message = vec![];
+        write_compact_size(&mut message, 256).unwrap();
+        write_compact_size(&mut message, 20).unwrap();
+        message.extend_from_slice(&[0x11; 20]);
+        message.extend_from_slice(&padding("u").unwrap());
+        let encoded = bech32m::encode("u", &f4jumble::jumble(&message));
+
+        assert!(decode_unified_address("u", &encoded).is_err());
+    }
+}
@@ -0,0 +1,8 @@
+//! Structs for handling the legacy, transparent half of the Zcash protocol.
+
+/// A transparent address corresponding to either a public key or a script.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TransparentAddress {
+    PublicKey([u8; 20]),
+    Script([u8; 20]),
+}
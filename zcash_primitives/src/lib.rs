@@ -16,6 +16,7 @@ extern crate sha2;
 use sapling_crypto::jubjub::JubjubBls12;
 
 pub mod block;
+pub mod legacy;
 pub mod merkle_tree;
 pub mod sapling;
 mod serialize;